@@ -0,0 +1,5 @@
+mod trie;
+mod trie_node;
+
+pub use trie::{ByteTrie, Trie};
+pub use trie_node::TrieValueType;
@@ -4,18 +4,70 @@ use super::trie_node::{TrieNode, TrieNodeType, TrieValueType};
 /// Based on Sedgewick.
 /// See "Ternary Search Trees" by Jon Bentley and Robert Sedgewick
 /// in the April, 1998, Dr. Dobb's Journal.
-/// 
+///
 /// Each TST node has a 1 byte key.  This is matched byte-by-byte with
 /// some input string.
-/// 
+///
 /// Each TST node has a dictionary `value` field that is used in the compressed
 /// output version of the string.  A TST node may have a None value if it
 /// is not associated with a dictionary key.
-pub struct Trie {
-    root: Option<TrieNodeType>,
+///
+/// `Trie` is generic over the value type `V` so it can be used as a general
+/// byte-keyed map; `ByteTrie` is a type alias for the original byte-vector
+/// dictionary use case.
+///
+/// With the `serde` feature enabled, a `Trie<V>` (for `V: Serialize +
+/// Deserialize`) round-trips through any serde format; deserialization
+/// validates that `size` matches the number of valued nodes found in `root`.
+pub struct Trie<V> {
+    root: Option<TrieNodeType<V>>,
     size: usize
 }
 
+/// A trie whose values are reference-counted byte vectors, matching the
+/// original dictionary-compression use case.
+pub type ByteTrie = Trie<TrieValueType>;
+
+#[cfg(feature = "serde")]
+impl<V: Clone + serde::Serialize> serde::Serialize for Trie<V> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        use serde::ser::SerializeStruct;
+        let mut state = serializer.serialize_struct("Trie", 2)?;
+        state.serialize_field("root", &self.root)?;
+        state.serialize_field("size", &self.size)?;
+        state.end()
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de, V: Clone + serde::Deserialize<'de>> serde::Deserialize<'de> for Trie<V> {
+    /// Reconstructs a `Trie` from its wire shape, validating that `size`
+    /// matches the number of valued nodes actually present in `root`.
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        #[derive(serde::Deserialize)]
+        struct TrieRepr<V> {
+            root: Option<TrieNodeType<V>>,
+            size: usize,
+        }
+
+        let repr = TrieRepr::deserialize(deserializer)?;
+        let counted = Trie::count_values(&repr.root);
+        if counted != repr.size {
+            return Err(serde::de::Error::custom(format!(
+                "Trie size {} does not match {} valued nodes found on deserialize",
+                repr.size, counted
+            )));
+        }
+        Ok(Trie { root: repr.root, size: repr.size })
+    }
+}
+
 macro_rules! allocate_if {
     ($ptr:expr, $key:expr) => {
         match $ptr {
@@ -24,13 +76,13 @@ macro_rules! allocate_if {
     };
 }
 
-impl Default for Trie {
+impl<V: Clone> Default for Trie<V> {
     fn default() -> Self {
         Self::new()
     }
 }
 
-impl Trie {
+impl<V: Clone> Trie<V> {
     pub fn new() -> Self {
         Trie { root: None, size: 0 }
     }
@@ -43,17 +95,84 @@ impl Trie {
         self.size == 0
     }
 
+    /// Builds a trie from `entries` balanced by the median-partition
+    /// technique, bounding left/right BST fan-out to `O(log k)` per
+    /// character position regardless of insertion order.
+    ///
+    /// Sorts `entries` by key, then at each middle-branch level recursively
+    /// picks the median distinct byte among the candidates as that level's
+    /// BST root and recurses on the lower/higher halves for left/right.  The
+    /// result holds the same contents as repeated `insert`, just shaped
+    /// differently.
+    pub fn from_sorted(entries: &[(&[u8], V)]) -> Self {
+        for (key, _) in entries {
+            assert!(!key.is_empty());
+        }
+
+        let mut sorted: Vec<(&[u8], V)> = entries.to_vec();
+        sorted.sort_by(|a, b| a.0.cmp(b.0));
+
+        for pair in sorted.windows(2) {
+            if pair[0].0 == pair[1].0 {
+                panic!("There is already a value at key {:?}", pair[0].0);
+            }
+        }
+
+        let size = sorted.len();
+        let root = Trie::recursive_build(&sorted, 0);
+        Trie { root, size }
+    }
+
+    /// Builds a balanced subtree from `entries` (sorted, all with a byte at
+    /// `depth`): partitions `entries` into contiguous runs that share the
+    /// byte at `depth`, picks the median run as the node, and recurses on
+    /// the lower/higher runs for `left`/`right` and the median run's
+    /// remainder (dropping an exact match at this depth) for `middle`.
+    fn recursive_build(entries: &[(&[u8], V)], depth: usize) -> Option<TrieNodeType<V>> {
+        if entries.is_empty() {
+            return None;
+        }
+
+        let mut run_starts = vec![0];
+        for i in 1..entries.len() {
+            if entries[i].0[depth] != entries[i - 1].0[depth] {
+                run_starts.push(i);
+            }
+        }
+        run_starts.push(entries.len());
+
+        let run_count = run_starts.len() - 1;
+        let mid = run_count / 2;
+        let run_start = run_starts[mid];
+        let run_end = run_starts[mid + 1];
+        let run = &entries[run_start..run_end];
+        let key = run[0].0[depth];
+
+        let (value, middle_entries) = if run[0].0.len() == depth + 1 {
+            (Some(run[0].1.clone()), &run[1..])
+        } else {
+            (None, run)
+        };
+
+        let mut node = TrieNode::new(&key, value);
+        node.left = Trie::recursive_build(&entries[..run_start], depth);
+        node.right = Trie::recursive_build(&entries[run_end..], depth);
+        node.middle = Trie::recursive_build(middle_entries, depth + 1);
+
+        Some(node)
+    }
+
     /// Inserts a value into the trie for the token string.
-    /// 
+    ///
     /// If there is already a value for the token string, panics.
-    pub fn insert(&mut self, tokens: &[u8], value: &TrieValueType) {
+    pub fn insert(&mut self, tokens: &[u8], value: &V) {
         assert!(!tokens.is_empty());
         allocate_if!(self.root, tokens[0]);
         Trie::recursive_insert(self.root.as_mut(), tokens, 0, value);
         self.size += 1;
     }
 
-    fn recursive_insert(node: Option<&mut TrieNodeType>, tokens: &[u8], offset: usize, value: &TrieValueType) {
+    fn recursive_insert(node: Option<&mut TrieNodeType<V>>, tokens: &[u8], offset: usize, value: &V) {
         let key = tokens[offset];
 
         let inner = match node {
@@ -61,7 +180,7 @@ impl Trie {
             // todo add error handling
             None => panic!("Should never happen")
         };
-        
+
         if key < inner.key {
             allocate_if!(inner.left, key);
             Trie::recursive_insert(inner.left.as_mut(), tokens, offset, value);
@@ -73,7 +192,7 @@ impl Trie {
             if (offset + 1) == tokens.len() {
                 // last token
                 if inner.value.is_some() {
-                    panic!("There is already a value at node {}", inner);
+                    panic!("There is already a value at key byte {:#x} (offset {})", inner.key, offset);
                 }
                 inner.value = Some(value.clone());
             } else {
@@ -85,9 +204,9 @@ impl Trie {
 
     /// Searches the trie for the token string and returns the value
     /// of the exact match node.  Will return None if not found.
-    pub fn search(&mut self, tokens: &[u8]) -> Option<TrieValueType> {
+    pub fn search(&mut self, tokens: &[u8]) -> Option<V> {
         let mut node = &mut self.root;
-        
+
         let mut offset: usize = 0;
         while offset < tokens.len() {
             let Some(box_node) = node else { return None };
@@ -111,23 +230,102 @@ impl Trie {
         None
     }
 
+    /// Removes the value for the token string, pruning any nodes that are left
+    /// with no value and no children along the way.
+    ///
+    /// Returns the removed value, or `None` if the token string was not found.
+    pub fn remove(&mut self, tokens: &[u8]) -> Option<V> {
+        if tokens.is_empty() {
+            return None;
+        }
+
+        let removed = Trie::recursive_remove(&mut self.root, tokens, 0);
+        if removed.is_some() {
+            self.size -= 1;
+        }
+        removed
+    }
+
+    /// Removes `tokens[offset..]` from the subtree at `node`.
+    ///
+    /// On unwind, a node whose `value` is `None` and whose `left`, `middle`,
+    /// and `right` are all `None` is pruned by replacing it with `None` in
+    /// its parent's slot (which is exactly `node` here).
+    fn recursive_remove(node: &mut Option<TrieNodeType<V>>, tokens: &[u8], offset: usize) -> Option<V> {
+        let inner = node.as_mut()?;
+        let key = tokens[offset];
+
+        let removed = if key < inner.key {
+            Trie::recursive_remove(&mut inner.left, tokens, offset)
+        } else if key > inner.key {
+            Trie::recursive_remove(&mut inner.right, tokens, offset)
+        } else if (offset + 1) == tokens.len() {
+            inner.value.take()
+        } else {
+            Trie::recursive_remove(&mut inner.middle, tokens, offset + 1)
+        };
+
+        if removed.is_some()
+            && inner.value.is_none()
+            && inner.left.is_none()
+            && inner.middle.is_none()
+            && inner.right.is_none()
+        {
+            *node = None;
+        }
+
+        removed
+    }
+
     /// Finds the longest matching string for tokens.
-    /// 
-    /// On success, returns the TrieValueType and the number of bytes consumed.
-    /// 
-    pub fn longest_match(&self, tokens: &[u8]) -> Option<(TrieValueType, usize)> {
-        let mut longest_match: Option<(TrieValueType, usize)> = None;
-        let mut longest_node: Option<TrieNodeType> = None;
+    ///
+    /// On success, returns the value and the number of bytes consumed, and
+    /// bumps `uses` on the node that matched, in a single mutable descent.
+    pub fn longest_match(&mut self, tokens: &[u8]) -> Option<(V, usize)> {
+        if tokens.is_empty() {
+            return None;
+        }
+        Trie::recursive_longest_match(self.root.as_mut(), tokens, 0)
+    }
+
+    /// Descends `node` along `tokens[offset..]`, preferring the longest
+    /// (deepest) match found on the way down and falling back to a shorter
+    /// match on the way back up. Bumps `uses` on the node whose value is
+    /// actually returned.
+    fn recursive_longest_match(node: Option<&mut TrieNodeType<V>>, tokens: &[u8], offset: usize) -> Option<(V, usize)> {
+        let inner = node?;
+        let key = tokens[offset];
+
+        if key < inner.key {
+            Trie::recursive_longest_match(inner.left.as_mut(), tokens, offset)
+        } else if key > inner.key {
+            Trie::recursive_longest_match(inner.right.as_mut(), tokens, offset)
+        } else {
+            let next_offset = offset + 1;
+            if next_offset < tokens.len() {
+                if let Some(deeper) = Trie::recursive_longest_match(inner.middle.as_mut(), tokens, next_offset) {
+                    return Some(deeper);
+                }
+            }
+            let value = inner.value.clone()?;
+            inner.uses += 1;
+            Some((value, next_offset))
+        }
+    }
+
+    /// Finds every dictionary entry that is a prefix of `tokens`, not just the
+    /// longest one.
+    ///
+    /// Follows the same left/middle/right path as `longest_match`, but pushes a
+    /// `(value, offset)` pair every time the path passes through a node with
+    /// `Some(value)`.  Results are in increasing-length order.
+    pub fn all_prefixes(&self, tokens: &[u8]) -> Vec<(V, usize)> {
+        let mut matches = Vec::new();
         let mut node = &self.root;
 
         let mut offset: usize = 0;
         while offset < tokens.len() {
-            let Some(box_node) = node else { 
-                if let Some(mut longest_node) = longest_node {
-                    longest_node.uses += 1;
-                }
-                return longest_match 
-            };
+            let Some(box_node) = node else { return matches };
 
             let key = tokens[offset];
 
@@ -138,20 +336,159 @@ impl Trie {
             } else {
                 // middle key
                 offset += 1;
-                if box_node.value.is_some() {
-                    // get a clone of the Rc out of the Option
-                    let value = box_node.value.as_ref().unwrap().clone();
-                    longest_match = Some((value, offset));
-                    longest_node = node.clone();
+                if let Some(value) = &box_node.value {
+                    matches.push((value.clone(), offset));
                 }
                 node = &box_node.middle;
             }
         }
+        matches
+    }
+
+    /// Returns an in-order iterator over every `(key, value)` pair stored in
+    /// the trie, in lexicographic order of the byte-keys.
+    pub fn iter(&self) -> impl Iterator<Item = (Vec<u8>, V)> {
+        let mut results = Vec::new();
+        Trie::recursive_iter(&self.root, &mut Vec::new(), &mut results);
+        results.into_iter()
+    }
+
+    /// In-order walk of the subtree at `node`, reconstructing each full key
+    /// in `key` as the middle branch is descended and yielding a pair into
+    /// `results` whenever a node has `Some(value)`.
+    fn recursive_iter(node: &Option<TrieNodeType<V>>, key: &mut Vec<u8>, results: &mut Vec<(Vec<u8>, V)>) {
+        let Some(inner) = node else { return };
+
+        Trie::recursive_iter(&inner.left, key, results);
+
+        key.push(inner.key);
+        if let Some(value) = &inner.value {
+            results.push((key.clone(), value.clone()));
+        }
+        Trie::recursive_iter(&inner.middle, key, results);
+        key.pop();
+
+        Trie::recursive_iter(&inner.right, key, results);
+    }
+
+    /// Returns every `(key, value)` pair whose key is a strict extension of
+    /// `prefix` (longer than `prefix`, not just equal to it) — the
+    /// "postfix" query: descend to the node ending `prefix` via the usual
+    /// comparison loop, then enumerate every valued node in that node's
+    /// middle subtree. If `prefix` itself is a stored key, its own value is
+    /// not included.
+    pub fn keys_with_prefix(&self, prefix: &[u8]) -> Vec<(Vec<u8>, V)> {
+        let mut results = Vec::new();
+
+        if prefix.is_empty() {
+            Trie::recursive_iter(&self.root, &mut Vec::new(), &mut results);
+            return results;
+        }
+
+        let mut node = &self.root;
+        let mut offset: usize = 0;
+        while offset < prefix.len() {
+            let Some(inner) = node else { return results };
+
+            let key = prefix[offset];
+            if key < inner.key {
+                node = &inner.left;
+            } else if key > inner.key {
+                node = &inner.right;
+            } else {
+                offset += 1;
+                if offset == prefix.len() {
+                    let mut matched = prefix.to_vec();
+                    Trie::recursive_iter(&inner.middle, &mut matched, &mut results);
+                    return results;
+                }
+                node = &inner.middle;
+            }
+        }
+        results
+    }
+
+    /// Counts nodes with `Some(value)` in the subtree at `node`, used to
+    /// validate `size` when deserializing.
+    #[cfg(feature = "serde")]
+    fn count_values(node: &Option<TrieNodeType<V>>) -> usize {
+        let Some(inner) = node else { return 0 };
+
+        let mut count = usize::from(inner.value.is_some());
+        count += Trie::count_values(&inner.left);
+        count += Trie::count_values(&inner.middle);
+        count += Trie::count_values(&inner.right);
+        count
+    }
+
+    /// Clears the value of (and then prunes) every entry whose `uses`
+    /// counter is below `min_uses`, trimming the trie down to the entries
+    /// that are actually paying off.
+    pub fn prune_unused(&mut self, min_uses: usize) {
+        self.size -= Trie::recursive_prune_unused(&mut self.root, min_uses);
+    }
+
+    /// Clears undervalued entries in the subtree at `node`, pruning any node
+    /// left with no value and no children, exactly as `remove` does.
+    /// Returns the number of entries cleared.
+    fn recursive_prune_unused(node: &mut Option<TrieNodeType<V>>, min_uses: usize) -> usize {
+        let Some(inner) = node.as_mut() else { return 0 };
+
+        let mut cleared = 0;
+        cleared += Trie::recursive_prune_unused(&mut inner.left, min_uses);
+        cleared += Trie::recursive_prune_unused(&mut inner.middle, min_uses);
+        cleared += Trie::recursive_prune_unused(&mut inner.right, min_uses);
+
+        if inner.value.is_some() && inner.uses < min_uses {
+            inner.value = None;
+            cleared += 1;
+        }
+
+        if inner.value.is_none()
+            && inner.left.is_none()
+            && inner.middle.is_none()
+            && inner.right.is_none()
+        {
+            *node = None;
+        }
+
+        cleared
+    }
+}
+
+impl Trie<TrieValueType> {
+    /// Greedily tokenizes `input` against this dictionary: at each offset,
+    /// emits the longest matching entry and advances past it, falling back
+    /// to a one-byte literal token when nothing matches so unmatched bytes
+    /// don't stall encoding.
+    pub fn encode(&mut self, input: &[u8]) -> Vec<TrieValueType> {
+        let mut tokens = Vec::new();
+
+        let mut offset = 0;
+        while offset < input.len() {
+            match self.longest_match(&input[offset..]) {
+                Some((value, consumed)) => {
+                    tokens.push(value);
+                    offset += consumed;
+                }
+                None => {
+                    tokens.push(TrieValueType::new(vec![input[offset]]));
+                    offset += 1;
+                }
+            }
+        }
+        tokens
+    }
 
-        if let Some(mut longest_node) = longest_node {
-            longest_node.uses += 1;
+    /// Concatenates the token strings produced by `encode`.  The result is
+    /// the original input only when every dictionary entry's value is its
+    /// key's own bytes; otherwise it is the concatenation of those values.
+    pub fn decode(tokens: &[TrieValueType]) -> Vec<u8> {
+        let mut output = Vec::new();
+        for token in tokens {
+            output.extend_from_slice(token);
         }
-        longest_match
+        output
     }
 }
 
@@ -161,14 +498,14 @@ mod tests {
 
     #[test]
     fn search_empty_trie() {
-        let mut t = Trie::new();
+        let mut t = ByteTrie::new();
         let result = t.search(&[0, 1, 2]);
         assert!(result.is_none());
     }
 
     #[test]
     fn insert_empty_trie() {
-        let mut t = Trie::new();
+        let mut t = ByteTrie::new();
         let value= TrieValueType::new(vec![2, 3]);
         let key = [5u8];
 
@@ -184,7 +521,7 @@ mod tests {
 
         #[test]
     fn insert_multi_byte() {
-        let mut t = Trie::new();
+        let mut t = ByteTrie::new();
         let value= TrieValueType::new(vec![2, 3]);
         let key = [5u8, 8u8, 9u8];
 
@@ -209,7 +546,7 @@ mod tests {
             ("yams", TrieValueType::new(vec![6u8]))
         ];
 
-        let mut t = Trie::new();
+        let mut t = ByteTrie::new();
 
         for (k, v) in &vectors {
             let key = k.as_bytes();
@@ -217,7 +554,7 @@ mod tests {
         }
 
         assert_eq!(vectors.len(), t.len());
-        
+
 
         for (k, v) in &vectors {
             let key = k.as_bytes();
@@ -241,7 +578,7 @@ mod tests {
             ("abcdefghi", Some((TrieValueType::new(vec![1u8]), 8)))
         ];
 
-        let mut t = Trie::new();
+        let mut t = ByteTrie::new();
 
         for (k, v) in &data {
             let key = k.as_bytes();
@@ -252,8 +589,273 @@ mod tests {
             let key = k.as_bytes();
             let actual = t.longest_match(key);
             assert_eq!(actual, *v, "Failed for key {}", k);
-        }        
+        }
     }
 
-}
+    #[test]
+    fn remove_missing_key() {
+        let mut t = ByteTrie::new();
+        let value = TrieValueType::new(vec![1u8]);
+        t.insert(b"apple", &value);
+
+        let actual = t.remove(b"grape");
+        assert!(actual.is_none());
+        assert_eq!(1, t.len());
+    }
+
+    #[test]
+    fn remove_leaf_key() {
+        let mut t = ByteTrie::new();
+        let value = TrieValueType::new(vec![1u8]);
+        t.insert(b"apple", &value);
+
+        let actual = t.remove(b"apple");
+        assert_eq!(Some(value), actual);
+        assert_eq!(0, t.len());
+        assert!(t.search(b"apple").is_none());
+        assert!(t.root.is_none());
+    }
+
+    #[test]
+    fn remove_prefix_leaves_middle_chain_intact() {
+        let mut t = ByteTrie::new();
+        let short_value = TrieValueType::new(vec![2u8]);
+        let long_value = TrieValueType::new(vec![1u8]);
+        t.insert(b"abcd", &short_value);
+        t.insert(b"abcdefgh", &long_value);
+
+        let actual = t.remove(b"abcd");
+        assert_eq!(Some(short_value), actual);
+        assert_eq!(1, t.len());
+        assert!(t.search(b"abcd").is_none());
+        assert_eq!(Some(long_value), t.search(b"abcdefgh"));
+    }
+
+    #[test]
+    fn iter_yields_keys_in_lexicographic_order() {
+        let vectors = vec![
+            ("grapefruit", TrieValueType::new(vec![1u8])),
+            ("grapes", TrieValueType::new(vec![2u8])),
+            ("apple", TrieValueType::new(vec![3u8])),
+            ("applesauce", TrieValueType::new(vec![4u8])),
+            ("jelly", TrieValueType::new(vec![5u8])),
+        ];
+
+        let mut t = ByteTrie::new();
+        for (k, v) in &vectors {
+            t.insert(k.as_bytes(), v);
+        }
 
+        let actual: Vec<(Vec<u8>, TrieValueType)> = t.iter().collect();
+        let expected: Vec<(Vec<u8>, TrieValueType)> = vec![
+            (b"apple".to_vec(), TrieValueType::new(vec![3u8])),
+            (b"applesauce".to_vec(), TrieValueType::new(vec![4u8])),
+            (b"grapefruit".to_vec(), TrieValueType::new(vec![1u8])),
+            (b"grapes".to_vec(), TrieValueType::new(vec![2u8])),
+            (b"jelly".to_vec(), TrieValueType::new(vec![5u8])),
+        ];
+        assert_eq!(expected, actual);
+    }
+
+    #[test]
+    fn keys_with_prefix_finds_postfixes() {
+        let mut t = ByteTrie::new();
+        t.insert(b"grapefruit", &TrieValueType::new(vec![1u8]));
+        t.insert(b"grapes", &TrieValueType::new(vec![2u8]));
+        t.insert(b"grape", &TrieValueType::new(vec![3u8]));
+        t.insert(b"apple", &TrieValueType::new(vec![4u8]));
+
+        let mut actual = t.keys_with_prefix(b"grape");
+        actual.sort();
+        let mut expected = vec![
+            (b"grapefruit".to_vec(), TrieValueType::new(vec![1u8])),
+            (b"grapes".to_vec(), TrieValueType::new(vec![2u8])),
+        ];
+        expected.sort();
+        assert_eq!(expected, actual);
+    }
+
+    #[test]
+    fn keys_with_prefix_missing_prefix_is_empty() {
+        let mut t = ByteTrie::new();
+        t.insert(b"apple", &TrieValueType::new(vec![1u8]));
+
+        let actual = t.keys_with_prefix(b"banana");
+        assert!(actual.is_empty());
+    }
+
+    #[test]
+    fn test_all_prefixes() {
+        let data = vec![
+            ("abcdefgh", TrieValueType::new(vec![1u8])),
+            ("abcd", TrieValueType::new(vec![2u8])),
+            ("ab", TrieValueType::new(vec![3u8])),
+        ];
+
+        let mut t = ByteTrie::new();
+        for (k, v) in &data {
+            t.insert(k.as_bytes(), v);
+        }
+
+        let actual = t.all_prefixes(b"abcdefghi");
+        let expected = vec![
+            (TrieValueType::new(vec![3u8]), 2),
+            (TrieValueType::new(vec![2u8]), 4),
+            (TrieValueType::new(vec![1u8]), 8),
+        ];
+        assert_eq!(expected, actual);
+
+        let actual = t.all_prefixes(b"xyz");
+        assert!(actual.is_empty());
+    }
+
+    #[test]
+    fn generic_value_type() {
+        let mut t: Trie<u32> = Trie::new();
+        t.insert(b"apple", &42u32);
+        t.insert(b"apricot", &7u32);
+
+        assert_eq!(Some(42u32), t.search(b"apple"));
+        assert_eq!(Some((42u32, 5)), t.longest_match(b"applesauce"));
+
+        let mut keys: Vec<(Vec<u8>, u32)> = t.iter().collect();
+        keys.sort();
+        assert_eq!(vec![(b"apple".to_vec(), 42u32), (b"apricot".to_vec(), 7u32)], keys);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_round_trip() {
+        let mut t = ByteTrie::new();
+        t.insert(b"apple", &TrieValueType::new(vec![1u8]));
+        t.insert(b"applesauce", &TrieValueType::new(vec![2u8]));
+        t.insert(b"grape", &TrieValueType::new(vec![3u8]));
+
+        let encoded = serde_json::to_string(&t).unwrap();
+        let decoded: ByteTrie = serde_json::from_str(&encoded).unwrap();
+
+        assert_eq!(t.len(), decoded.len());
+        let mut expected: Vec<_> = t.iter().collect();
+        let mut actual: Vec<_> = decoded.iter().collect();
+        expected.sort();
+        actual.sort();
+        assert_eq!(expected, actual);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_rejects_size_mismatch() {
+        let mut t = ByteTrie::new();
+        t.insert(b"apple", &TrieValueType::new(vec![1u8]));
+
+        let mut encoded: serde_json::Value = serde_json::to_value(&t).unwrap();
+        encoded["size"] = serde_json::Value::from(99usize);
+
+        let result: Result<ByteTrie, _> = serde_json::from_value(encoded);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn encode_decode_round_trip() {
+        // Dictionary entries whose value is the key's own bytes (interned
+        // via Rc) round-trip through encode/decode; unmatched bytes fall
+        // back to one-byte literal tokens.
+        let mut t = ByteTrie::new();
+        t.insert(b"ab", &TrieValueType::new(b"ab".to_vec()));
+        t.insert(b"cd", &TrieValueType::new(b"cd".to_vec()));
+
+        let tokens = t.encode(b"abXcd");
+        assert_eq!(
+            vec![
+                TrieValueType::new(b"ab".to_vec()),
+                TrieValueType::new(b"X".to_vec()),
+                TrieValueType::new(b"cd".to_vec()),
+            ],
+            tokens
+        );
+
+        assert_eq!(b"abXcd".to_vec(), ByteTrie::decode(&tokens));
+    }
+
+    #[test]
+    fn prune_unused_clears_entries_below_threshold() {
+        let mut t = ByteTrie::new();
+        t.insert(b"apple", &TrieValueType::new(vec![1u8]));
+        t.insert(b"applesauce", &TrieValueType::new(vec![2u8]));
+
+        t.search(b"apple");
+        t.search(b"apple");
+
+        t.prune_unused(2);
+
+        assert_eq!(1, t.len());
+        assert!(t.search(b"apple").is_some());
+        assert!(t.search(b"applesauce").is_none());
+    }
+
+    #[test]
+    fn prune_unused_credits_encode_driven_usage() {
+        let mut t = ByteTrie::new();
+        t.insert(b"ab", &TrieValueType::new(b"ab".to_vec()));
+        t.insert(b"cd", &TrieValueType::new(b"cd".to_vec()));
+
+        for _ in 0..3 {
+            t.encode(b"ab");
+        }
+
+        t.prune_unused(3);
+
+        assert!(t.search(b"ab").is_some());
+        assert!(t.search(b"cd").is_none());
+    }
+
+    #[test]
+    fn from_sorted_matches_naive_insert() {
+        let data: Vec<(&[u8], TrieValueType)> = vec![
+            (b"apple", TrieValueType::new(vec![1u8])),
+            (b"applesauce", TrieValueType::new(vec![2u8])),
+            (b"grape", TrieValueType::new(vec![3u8])),
+            (b"grapefruit", TrieValueType::new(vec![4u8])),
+            (b"grapes", TrieValueType::new(vec![5u8])),
+            (b"jelly", TrieValueType::new(vec![6u8])),
+            (b"yams", TrieValueType::new(vec![7u8])),
+        ];
+
+        let mut naive = ByteTrie::new();
+        for (k, v) in &data {
+            naive.insert(k, v);
+        }
+
+        let mut balanced = ByteTrie::from_sorted(&data);
+
+        assert_eq!(naive.len(), balanced.len());
+        for (k, v) in &data {
+            assert_eq!(Some(v.clone()), balanced.search(k));
+        }
+        for (k, _) in &data {
+            assert_eq!(naive.search(k), balanced.search(k));
+        }
+        assert!(balanced.search(b"missing").is_none());
+    }
+
+    #[test]
+    #[should_panic]
+    fn from_sorted_rejects_empty_key() {
+        let data: Vec<(&[u8], TrieValueType)> = vec![
+            (b"apple", TrieValueType::new(vec![1u8])),
+            (b"", TrieValueType::new(vec![2u8])),
+        ];
+        ByteTrie::from_sorted(&data);
+    }
+
+    #[test]
+    #[should_panic(expected = "There is already a value at key")]
+    fn from_sorted_rejects_duplicate_key() {
+        let data: Vec<(&[u8], TrieValueType)> = vec![
+            (b"apple", TrieValueType::new(vec![1u8])),
+            (b"apple", TrieValueType::new(vec![2u8])),
+        ];
+        ByteTrie::from_sorted(&data);
+    }
+
+}
@@ -2,24 +2,26 @@ use std::rc::Rc;
 
 /// This type is only used in Trie.  We box it for use in
 ///  the TrieNode left, middle, right pointers.
-pub (super) type TrieNodeType = Box<TrieNode>;
+pub (super) type TrieNodeType<V> = Box<TrieNode<V>>;
 
-/// This type is used outside of the module
+/// Convenience value type for a byte-vector dictionary, kept so existing
+/// callers of `ByteTrie` (a `Trie<TrieValueType>`) compile unchanged.
 pub type TrieValueType = Rc<Vec<u8>>;
 
 #[derive(Clone, Debug, PartialEq)]
-pub (super) struct TrieNode {
-    pub left: Option<TrieNodeType>,
-    pub middle: Option<TrieNodeType>,
-    pub right: Option<TrieNodeType>,
-    pub value: Option<TrieValueType>,
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub (super) struct TrieNode<V> {
+    pub left: Option<TrieNodeType<V>>,
+    pub middle: Option<TrieNodeType<V>>,
+    pub right: Option<TrieNodeType<V>>,
+    pub value: Option<V>,
     pub key: u8,
 
     /// Number of ties fetched
     pub uses: usize,
 }
 
-impl std::fmt::Display for TrieNode {
+impl<V: std::fmt::Debug> std::fmt::Display for TrieNode<V> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(
             f,
@@ -29,8 +31,8 @@ impl std::fmt::Display for TrieNode {
     }
 }
 
-impl TrieNode {
-    pub fn new(key: &u8, value: Option<TrieValueType>) -> TrieNodeType {
+impl<V: Clone> TrieNode<V> {
+    pub fn new(key: &u8, value: Option<V>) -> TrieNodeType<V> {
         Box::new(TrieNode {
             left: None,
             middle: None,